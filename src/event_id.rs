@@ -0,0 +1,79 @@
+use std::{
+    any,
+    fmt::{self, Display, Debug, Formatter},
+    marker::PhantomData,
+};
+
+/// Identifies a single logical event as it travels from the `Feed` which sent it through every reader which consumes it.
+///
+/// An `EventId` is assigned by the `Feed` at send time and is shared by every reader which sees that event — unlike the event's value, which is handed out separately to each reader, the id lets you recognize that two deliveries are "the same event" for tracing and metrics. Ids are assigned in sending order and never reused. While the feed has a live mutator, the id is assigned when the event leaves the last mutator stage rather than when it is sent.
+pub struct EventId<Evt> {
+    index: usize,
+    _marker: PhantomData<fn() -> Evt>,
+}
+impl<Evt> EventId<Evt> {
+    #[inline(always)]
+    pub(crate) fn new(index: usize) -> Self {
+        Self {index, _marker: PhantomData}
+    }
+    /// The position of this event in the total order of everything ever sent by the feed which produced it.
+    #[inline(always)]
+    pub fn index(self) -> usize {
+        self.index
+    }
+}
+impl<Evt> Clone for EventId<Evt> {
+    #[inline(always)]
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+impl<Evt> Copy for EventId<Evt> {}
+impl<Evt> PartialEq for EventId<Evt> {
+    #[inline(always)]
+    fn eq(&self, other: &Self) -> bool {
+        self.index == other.index
+    }
+}
+impl<Evt> Eq for EventId<Evt> {}
+impl<Evt> std::hash::Hash for EventId<Evt> {
+    #[inline(always)]
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.index.hash(state);
+    }
+}
+impl<Evt> Debug for EventId<Evt> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "event<{}>#{}", any::type_name::<Evt>(), self.index)
+    }
+}
+impl<Evt> Display for EventId<Evt> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        Debug::fmt(self, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Feed, EventId};
+    use parking_lot::Mutex;
+    use std::sync::Arc;
+
+    #[test]
+    fn same_event_keeps_its_id_through_every_reader_and_traces_send_then_consume() {
+        let mut feed = Feed::<i32>::new();
+        let alice = feed.add_reader();
+        let bob = feed.add_reader();
+        let trace: Arc<Mutex<Vec<usize>>> = Arc::new(Mutex::new(Vec::new()));
+        let recorded = Arc::clone(&trace);
+        feed.set_trace_hook(Some(move |id: EventId<i32>| recorded.lock().push(id.index())));
+
+        feed.send(42);
+        let (id_a, _) = alice.read_with_ids().next().unwrap();
+        let (id_b, _) = bob.read_with_ids().next().unwrap();
+        assert_eq!(id_a, id_b, "every reader should see the same id for the same event");
+        assert_eq!(id_a.index(), 0);
+
+        assert_eq!(*trace.lock(), vec![0, 0, 0], "hook should fire once on send and once per reader's consume");
+    }
+}
@@ -0,0 +1,205 @@
+use std::{
+    fmt::{self, Formatter},
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+};
+use parking_lot::Mutex;
+
+/// The two buffers an `ExpiringFeed` keeps: the events sent since the last `update()` call, and the ones sent in the frame before that.
+struct Buffers<Evt> {
+    /// Events sent since the last `update()`.
+    current: Vec<Arc<Evt>>,
+    /// The absolute index of `current`'s first element.
+    current_start: usize,
+    /// Events sent in the frame before the current one. Dropped wholesale on the next `update()`.
+    previous: Vec<Arc<Evt>>,
+    /// The absolute index of `previous`'s first element.
+    previous_start: usize,
+    /// The absolute index which the next sent event will receive, i.e. the total number of events ever sent.
+    sent: usize,
+}
+
+/// A feed with bounded, leak-free memory usage, meant for frame- or tick-based programs.
+///
+/// Unlike `Feed`, which keeps every event alive until the slowest reader has read it, an `ExpiringFeed` keeps only two buffers: the events sent since the last `update()` call, and the ones sent in the frame before that. Calling `update()` drops the older buffer and starts a new one, so every event survives for exactly two `update()` calls before being silently discarded — whether or not a reader has read it. This trades the guarantee that every event is seen for a guarantee that memory use never grows past what a single frame's worth of events requires, which is what a game loop usually wants instead.
+///
+/// Whether a reader sees an event sent near a frame boundary depends on whether `send` or `update` ran first: an event sent before `update()` is called is attributed to the frame that just ended, while one sent after belongs to the new frame. A reader which has not read in a while may therefore still see an event after one `update()` has passed since it was sent, but never after two.
+pub struct ExpiringFeed<Evt>
+where Evt: Send + 'static {
+    shared: Arc<Mutex<Buffers<Evt>>>,
+}
+impl<Evt> ExpiringFeed<Evt>
+where Evt: Send + 'static {
+    /// Creates a new expiring feed with both buffers empty.
+    #[inline]
+    pub fn new() -> Self {
+        Self {
+            shared: Arc::new(Mutex::new(Buffers {
+                current: Vec::new(),
+                current_start: 0,
+                previous: Vec::new(),
+                previous_start: 0,
+                sent: 0,
+            })),
+        }
+    }
+    /// Adds a new reader to the feed and returns it.
+    ///
+    /// The resulting structure can be freely sent and shared over threads. It will only see events sent from this point onward.
+    #[inline]
+    pub fn add_reader(&self) -> Arc<ExpiringReader<Evt>> {
+        let last_seen = self.shared.lock().sent;
+        Arc::new(ExpiringReader {
+            shared: Arc::clone(&self.shared),
+            last_seen: AtomicUsize::new(last_seen),
+        })
+    }
+    /// Sends an event to every reader by calling the specified closure once to produce it.
+    ///
+    /// The event is placed into the "this frame" buffer, where it remains readable until the next two calls to `update()` have passed.
+    ///
+    /// If your type implements `Clone`, simply using `send` would be more idiomatic.
+    pub fn send_with<F>(&self, f: F)
+    where F: FnOnce() -> Evt {
+        let mut buffers = self.shared.lock();
+        buffers.current.push(Arc::new(f()));
+        buffers.sent += 1;
+    }
+    /// Advances the feed by one frame: the "last frame" buffer is dropped, the "this frame" buffer becomes "last frame", and a new, empty "this frame" buffer is started.
+    ///
+    /// Any event which was already in the "last frame" buffer when this is called is discarded, whether or not every reader has read it. Call this once per tick, after every reader which cares about this frame's events has had the chance to read them.
+    pub fn update(&self) {
+        let mut buffers = self.shared.lock();
+        buffers.previous = std::mem::take(&mut buffers.current);
+        buffers.previous_start = buffers.current_start;
+        buffers.current_start = buffers.sent;
+    }
+}
+impl<Evt> ExpiringFeed<Evt>
+where Evt: Send + Clone + 'static {
+    /// Sends the specified event to each reader. The event is stored once and shared between readers behind an `Arc`; it is not cloned.
+    #[inline(always)]
+    pub fn send(&self, event: Evt) {
+        self.send_with(|| event)
+    }
+}
+impl<Evt> ExpiringFeed<Evt>
+where Evt: Send + Default + 'static {
+    /// Sends the default value of the event.
+    #[inline(always)]
+    pub fn send_default(&self) {
+        self.send_with(Default::default)
+    }
+}
+impl<Evt> Clone for ExpiringFeed<Evt>
+where Evt: Send + 'static {
+    /// Clones the feed by creating a new handle onto the same underlying buffers and readers.
+    #[inline(always)]
+    fn clone(&self) -> Self {
+        Self {
+            shared: Arc::clone(&self.shared),
+        }
+    }
+}
+impl<Evt> Default for ExpiringFeed<Evt>
+where Evt: Send + 'static {
+    #[inline(always)]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl<Evt> fmt::Debug for ExpiringFeed<Evt>
+where Evt: Send + 'static {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let buffers = self.shared.lock();
+        f.debug_struct("ExpiringFeed")
+            .field("current_len", &buffers.current.len())
+            .field("previous_len", &buffers.previous.len())
+            .field("sent", &buffers.sent)
+        .finish()
+    }
+}
+
+/// Recieves events from an `ExpiringFeed` by tracking the count of the last event it has seen.
+///
+/// Events older than the feed's last `update()` call before the previous one are silently skipped if a reader has not gotten to them by the time it calls `read`, since the buffer holding them no longer exists.
+pub struct ExpiringReader<Evt>
+where Evt: Send + 'static {
+    shared: Arc<Mutex<Buffers<Evt>>>,
+    last_seen: AtomicUsize,
+}
+impl<Evt> ExpiringReader<Evt>
+where Evt: Send + 'static {
+    /// Reads every event sent since this reader last read, which has not yet expired.
+    #[inline]
+    pub fn read(&self) -> Vec<Arc<Evt>> {
+        let buffers = self.shared.lock();
+        let last_seen = self.last_seen.load(Ordering::Acquire);
+        let mut result = Vec::new();
+        if last_seen < buffers.previous_start + buffers.previous.len() {
+            let skip = last_seen.saturating_sub(buffers.previous_start);
+            result.extend(buffers.previous[skip..].iter().cloned());
+        }
+        if last_seen < buffers.current_start + buffers.current.len() {
+            let skip = last_seen.saturating_sub(buffers.current_start).min(buffers.current.len());
+            result.extend(buffers.current[skip..].iter().cloned());
+        }
+        self.last_seen.store(buffers.sent, Ordering::Release);
+        result
+    }
+    /// Reads every pending, unexpired event by using the specified closure to process them.
+    #[inline]
+    pub fn read_with<F>(&self, mut f: F)
+    where F: FnMut(Arc<Evt>) {
+        for event in self.read() {
+            f(event);
+        }
+    }
+}
+impl<Evt> fmt::Debug for ExpiringReader<Evt>
+where Evt: Send + 'static {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.debug_struct("ExpiringReader")
+            .field("last_seen", &self.last_seen.load(Ordering::Relaxed))
+        .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn event_survives_exactly_two_updates() {
+        let feed = ExpiringFeed::<i32>::new();
+        let reader = feed.add_reader();
+
+        feed.send(1);
+        feed.update(); // 1 moves into "previous"
+        feed.send(2);
+        let got: Vec<i32> = reader.read().iter().map(|e| **e).collect();
+        assert_eq!(got, vec![1, 2], "reader should see both the previous and current frame's events");
+
+        feed.update(); // 1 expires, 2 moves into "previous"
+        feed.send(3);
+        feed.update(); // 2 expires, 3 moves into "previous"
+        let late_reader = feed.add_reader();
+        feed.send(4);
+        let got: Vec<i32> = late_reader.read().iter().map(|e| **e).collect();
+        assert_eq!(got, vec![4], "a reader added after event 2 expired must not see it");
+    }
+
+    #[test]
+    fn reader_which_lags_by_two_updates_misses_the_oldest_event() {
+        let feed = ExpiringFeed::<i32>::new();
+        let reader = feed.add_reader();
+        feed.send(1);
+        feed.update();
+        feed.update(); // event 1 has now been dropped from both buffers
+        feed.send(2);
+        let got: Vec<i32> = reader.read().iter().map(|e| **e).collect();
+        assert_eq!(got, vec![2], "expired event 1 must not appear");
+    }
+}
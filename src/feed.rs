@@ -1,107 +1,133 @@
 use std::{
-    sync::{
-        Arc, Weak as WArc,
-    },
+    fmt::{self, Formatter},
+    sync::Arc,
 };
+use parking_lot::Mutex;
 use crate::{
-    Reader,
+    Reader, EventId, Mutator, SharedReaderGroup,
+    reader::{Ring, Shared, TraceHook, Cursor},
+    mutator::Pipeline,
+    shared_group::GroupQueue,
 };
 
 /// An event feed — the source of events in a feed-based event system.
 ///
-/// Feeds are the source of readers, which are subscibers of the feed, capable of recieving the events from the feed.
-#[derive(Debug)]
+/// Feeds are the source of readers, which are subscibers of the feed, capable of recieving the events from the feed. All readers created from a feed share a single ring buffer of events rather than each keeping their own copy: `send` makes exactly one copy of the event no matter how many readers exist, and reading never clones it again. The ring only grows to accommodate whichever reader has fallen the furthest behind, and shrinks again once that reader catches up or is dropped.
 pub struct Feed<Evt>
-where Evt: Send {
-    readers: Vec<WArc<Reader<Evt>>>,
+where Evt: Send + 'static {
+    shared: Arc<Shared<Evt>>,
+    pipeline: Arc<Pipeline<Evt>>,
 }
 impl<Evt> Feed<Evt>
-where Evt: Send {
+where Evt: Send + 'static {
     /// Creates a new feed without any readers.
     ///
-    /// If you expect a certain number of readers, use `with_reader_capacity`.
+    /// If you expect readers to lag behind by a certain number of events, use `with_capacity`.
     #[inline(always)]
     pub fn new() -> Self {
-        Self {
-            readers: Vec::new(),
-        }
+        Self::with_capacity(0)
     }
-    /// Creates a new feed with internal storage allocated to be able to send to the specified amount of readers without reallocation.
+    /// Creates a new feed with its ring buffer preallocated to hold the specified number of events without reallocating.
     ///
-    /// If you know in advance how many readers you will have, use this method. Otherwise, use `new` for simplicity.
-    #[inline(always)]
-    pub fn with_reader_capacity(capacity: usize) -> Self {
+    /// If you do not know in advance how far behind your slowest reader will fall, use `new` for simplicity.
+    #[inline]
+    pub fn with_capacity(capacity: usize) -> Self {
         Self {
-            readers: Vec::with_capacity(capacity),
+            shared: Arc::new(Shared {
+                ring: Mutex::new(Ring::with_capacity(capacity)),
+                cursors: Mutex::new(Vec::new()),
+                groups: Mutex::new(Vec::new()),
+                trace: Mutex::new(None),
+            }),
+            pipeline: Arc::new(Pipeline::new()),
         }
     }
     /// Adds a new reader to the feed and returns it.
     ///
-    /// The resulting structure can be freely sent and shared over threads.
+    /// The resulting structure can be freely sent and shared over threads. Its cursor starts at the feed's current write position, so it will not see events sent before it was created.
     #[inline]
     pub fn add_reader(&mut self) -> Arc<Reader<Evt>> {
-        let reader = Arc::new(Reader::new());
-        self.readers.push(Arc::downgrade(&reader));
-        reader
+        // The ring stays locked from reading `next_index` through registering the cursor, so a
+        // concurrent `send` can't trim past the start position before anything is holding it back.
+        let ring = self.shared.ring.lock();
+        let cursor = Arc::new(Cursor::new(ring.next_index()));
+        self.shared.cursors.lock().push(Arc::downgrade(&cursor));
+        drop(ring);
+        Arc::new(Reader::new(Arc::clone(&self.shared), cursor))
     }
-    /// Sends an event to each reader by calling the specified closure once for each event.
+    /// Sends an event to every reader by calling the specified closure once to produce it.
+    ///
+    /// Unlike a per-reader-queue design, the closure is called exactly once per `send_with` call rather than once per reader, since every reader shares the single event it produces. If any reader has fallen behind, the ring grows to hold the new event instead of overwriting one that reader has not read yet.
+    ///
+    /// While any mutator added with `add_mutator` is still alive, the event is first funneled through their pipeline instead of going straight into the ring; it only becomes visible to ordinary readers once every mutator stage has had a chance to modify it.
     ///
     /// If your type implements `Clone`, simply using `send` would be more idiomatic.
-    pub fn send_with<F>(&self, mut f: F)
-    where F: FnMut() -> Evt {
-        for reader in &self.readers {
-            if let Some(reader) = reader.upgrade() {
-                reader.recieve(f());
-            }
+    pub fn send_with<F>(&self, f: F)
+    where F: FnOnce() -> Evt {
+        if self.pipeline.has_stages() {
+            self.pipeline.push(f());
+            return;
         }
+        let mut ring = self.shared.ring.lock();
+        let id = EventId::new(ring.next_index());
+        let event = Arc::new(f());
+        ring.queue.push_back(Arc::clone(&event));
+        // Trim here too rather than relying solely on some reader's `read()` to do it, so the
+        // ring stops growing as soon as its readers are gone even if none of them ever reads again.
+        self.shared.trim(&mut ring);
+        drop(ring);
+        self.shared.fan_out_to_groups(&event);
+        self.shared.trace(id);
+        #[cfg(feature = "futures")]
+        self.shared.wake_all();
+    }
+    /// Adds a new shared reader group to the feed and returns it.
+    ///
+    /// Every subscriber of the returned group pops events from one queue shared between them, so each event sent to the feed is delivered to exactly one subscriber rather than to all of them — complementing ordinary broadcast readers added with `add_reader`, which each see every event. A feed can have any number of shared reader groups alongside any number of broadcast readers; every send gives one shared copy to each group in addition to one clone to each broadcast reader.
+    #[inline]
+    pub fn add_shared_reader_group(&mut self) -> SharedReaderGroup<Evt> {
+        let queue = Arc::new(GroupQueue::new());
+        self.shared.groups.lock().push(Arc::downgrade(&queue));
+        SharedReaderGroup { queue }
     }
-    /// Removes references to dropped readers in order to release memory allocated for them and speed up calls to methods which send events.
+    /// Adds a new mutator stage to the feed's pipeline and returns a handle to it.
+    ///
+    /// Mutators are visited in the order they were added: the event is passed through every stage, each one able to observe and modify the changes made by those before it, before it becomes visible to readers added with `add_reader`. While any mutator is alive, events do not reach ordinary readers until they have passed through every stage whose mutator has not been dropped; once every mutator has been dropped, events go straight to readers again.
+    #[inline]
+    pub fn add_mutator(&mut self) -> Arc<Mutator<Evt>> {
+        let stage = self.pipeline.register();
+        Arc::new(Mutator::new(Arc::clone(&self.pipeline), Arc::clone(&self.shared), stage))
+    }
+    /// Removes references to dropped readers and shared reader groups in order to release memory allocated for tracking them and speed up calls to methods which send events.
+    ///
+    /// Note that events are released from the ring as soon as every live reader has read past them regardless of whether this is called; this only prunes bookkeeping left behind by readers and groups which no longer exist.
     pub fn remove_dangling_readers(&mut self) {
-        #[inline]
-        fn find_dead_on_end<Evt: Send>(readers: &Vec<WArc<Reader<Evt>>>, current: usize) -> usize {
-            let mut result = current;
-            for i in (readers.len() - 1 - current)..0 {
-                if readers[i].strong_count() == 0 {
-                    result += 1;
-                } else {break;}
-            }
-            result
-        }
-        // Keep track of how many dead readers we have on the end of the list.
-        let mut dead_on_end = find_dead_on_end(&self.readers, 0);
-        for i in 0..self.readers.len() {
-            // If we reached the part where all elements are dead readers, we are done.
-            if i >= self.readers.len() - dead_on_end {
-                break;
-            }
-            if self.readers[i].strong_count() == 0 {
-                // We found a dead reader. Let's move it to the end to remove them all quickly.
-                let location_on_end = self.readers.len() - 1 - dead_on_end;
-                self.readers.swap(i, location_on_end);
-                dead_on_end += 1;
-            }
-            // Update the count.
-            dead_on_end = find_dead_on_end(&self.readers, dead_on_end);
-        }
-        // Once we are here, all elements past a certain point are dead readers, which means that they can be removed.
-        let new_size = self.readers.len() - dead_on_end;
-        // Drop the elements past that point.
-        self.readers.truncate(new_size);
+        self.shared.cursors.lock().retain(|cursor| cursor.strong_count() > 0);
+        self.shared.groups.lock().retain(|group| group.strong_count() > 0);
+    }
+    /// Sets a callback to be invoked with an event's `EventId` whenever it is sent, and again whenever a reader consumes it, for tracing and metrics.
+    ///
+    /// While any mutator is alive, the "sent" call happens when the event leaves the last mutator stage rather than when `send` is called.
+    ///
+    /// Only one hook can be set at a time; calling this again replaces the previous one. Pass `None` to stop tracing.
+    #[inline]
+    pub fn set_trace_hook<F>(&mut self, hook: Option<F>)
+    where F: Fn(EventId<Evt>) + Send + Sync + 'static {
+        let hook: Option<TraceHook<Evt>> = hook.map(|hook| Arc::new(hook) as TraceHook<Evt>);
+        *self.shared.trace.lock() = hook;
     }
 }
 impl<Evt> Feed<Evt>
-where Evt: Send + Clone {
+where Evt: Send + Clone + 'static {
     /// Sends the specified event to each reader by cloning it.
     #[inline(always)]
     pub fn send(&self, event: Evt) {
-        self.send_with(|| event.clone())
+        self.send_with(|| event)
     }
 }
 impl<Evt> Feed<Evt>
-where Evt: Send + Default {
+where Evt: Send + Default + 'static {
     /// Sends the default value of the event.
-    ///
-    /// The `Default` implementation for the event type is called once per reader, even if it implements `Clone`.
     #[inline(always)]
     pub fn send_default(&self) {
         self.send_with(Default::default)
@@ -109,19 +135,88 @@ where Evt: Send + Default {
 }
 
 impl<Evt> Clone for Feed<Evt>
-where Evt: Send {
-    /// Clones the feed by creating a new feed which sends events to the same readers. The two feeds exist independently, i.e. adding a new reader to one of them will not modify another.
+where Evt: Send + 'static {
+    /// Clones the feed by creating a new handle onto the same underlying ring buffer and readers. The two feeds are not independent: sending through either one delivers to every reader created from either.
     #[inline(always)]
     fn clone(&self) -> Self {
         Self {
-            readers: self.readers.clone()
+            shared: Arc::clone(&self.shared),
+            pipeline: Arc::clone(&self.pipeline),
         }
     }
 }
 impl<Evt> Default for Feed<Evt>
-where Evt: Send {
+where Evt: Send + 'static {
     #[inline(always)]
     fn default() -> Self {
         Self::new()
     }
-}
\ No newline at end of file
+}
+impl<Evt> fmt::Debug for Feed<Evt>
+where Evt: Send + 'static {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let ring = self.shared.ring.lock();
+        f.debug_struct("Feed")
+            .field("len", &ring.queue.len())
+            .field("next_index", &ring.next_index())
+        .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{thread, time::{Duration, Instant}};
+
+    #[test]
+    fn ring_grows_for_lagging_reader_and_trims_once_it_catches_up() {
+        let mut feed = Feed::<i32>::new();
+        let fast = feed.add_reader();
+        let slow = feed.add_reader();
+        for i in 0..5 {
+            feed.send(i);
+        }
+        assert_eq!(fast.read().map(|e| *e).collect::<Vec<_>>(), vec![0, 1, 2, 3, 4]);
+        assert_eq!(format!("{:?}", feed), "Feed { len: 5, next_index: 5 }",
+            "ring must still hold every event the lagging reader hasn't read yet");
+        assert_eq!(slow.read().map(|e| *e).collect::<Vec<_>>(), vec![0, 1, 2, 3, 4]);
+        assert_eq!(format!("{:?}", feed), "Feed { len: 0, next_index: 5 }",
+            "ring should be trimmed once every reader has caught up");
+    }
+
+    #[test]
+    fn ring_stays_empty_once_every_reader_is_dropped() {
+        let mut feed = Feed::<i32>::new();
+        let reader = feed.add_reader();
+        feed.send(1);
+        drop(reader);
+        feed.send(2);
+        feed.send(3);
+        assert_eq!(format!("{:?}", feed), "Feed { len: 0, next_index: 3 }",
+            "send_with should trim opportunistically instead of only relying on a live reader's read()");
+    }
+
+    #[test]
+    fn one_readers_open_iterator_does_not_block_another_readers_read() {
+        let mut feed = Feed::<i32>::new();
+        let reader_a = feed.add_reader();
+        let reader_b = feed.add_reader();
+        feed.send(1);
+        feed.send(2);
+
+        let a = Arc::clone(&reader_a);
+        let handle = thread::spawn(move || {
+            let mut it = a.read();
+            let _first = it.next();
+            thread::sleep(Duration::from_millis(300));
+            drop(it);
+        });
+        thread::sleep(Duration::from_millis(50));
+        let start = Instant::now();
+        let _ = reader_b.read().next();
+        let elapsed = start.elapsed();
+        handle.join().unwrap();
+        assert!(elapsed < Duration::from_millis(200),
+            "reader_b.read() should not block behind reader_a's open iterator, took {:?}", elapsed);
+    }
+}
@@ -18,7 +18,7 @@
 //! - Since the events are processed separately from being produced, they can be processed anytime, and that includes postponed processing. This allows for intricate scheduling using completely unrelated libraries, all being totally painless since nothing happens immediately.
 //! - The sending part (the library object which produces events, i.e. the part which stores callbacks in the problematic case) does not store anything except for references to the readers, meaning that it always is `Send` and `Sync`. The only trait bound in this case is `Send` for the event type — the implementation takes care of the rest.
 //! - No dynamic dispatch is mandatory — the readers process the events using just one closure, which can combine multiple handlers for different types of events, or perform multiple actions for one type of event.
-//! - Posting an event into a feed still has `O(n)` complexity, but in this case `n` is the number of readers rather than the number of callbacks, and one reader does the job of multiple or all callbacks.
+//! - Posting an event into a feed stores it exactly once regardless of the number of readers: the feed keeps a single shared copy of the event in a ring buffer and every reader advances its own cursor through it, rather than the feed cloning the event into `n` separate per-reader queues.
 //!
 //! # Usage
 //! Basic usage:
@@ -33,10 +33,11 @@
 //! // Send an event through the feed.
 //! feed.send("Hello event feed!");
 //!
-//! // We can now read the event we sent.
+//! // We can now read the event we sent. Events are handed out behind an `Arc` since every
+//! // reader shares the same single copy of them.
 //! assert_eq!(
-//!     reader.read().next(),
-//!     Some("Hello event feed!"),
+//!     reader.read().next().as_deref(),
+//!     Some(&"Hello event feed!"),
 //! );
 //! // There are no more events in the feed.
 //! assert_eq!(
@@ -70,10 +71,31 @@ pub use feed::*;
 mod reader;
 pub use reader::*;
 
+mod expiring;
+pub use expiring::*;
+
+mod event_id;
+pub use event_id::*;
+
+mod mutator;
+pub use mutator::*;
+
+mod shared_group;
+pub use shared_group::*;
+
+#[cfg(feature = "futures")]
+mod stream;
+
 /// A prelude module which reexports a minimal set of types you need to use event feeds which are renamed specifically to be glob-imported without any name conflicts (`use event_feed::prelude::*`).
 pub mod prelude {
     pub use crate::{
         Feed as EventFeed,
         Reader as EventReader,
+        ExpiringFeed,
+        ExpiringReader,
+        EventId,
+        Mutator,
+        SharedReaderGroup,
+        SharedReader as SharedEventReader,
     };
 }
\ No newline at end of file
@@ -0,0 +1,207 @@
+use std::{
+    collections::VecDeque,
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+    fmt::{self, Formatter},
+};
+use parking_lot::Mutex;
+use crate::{EventId, reader::Shared};
+
+/// An event which has been sent but has not yet passed through every registered mutator stage.
+struct Staged<Evt> {
+    event: Evt,
+    /// How many mutator stages have already been applied to this event.
+    progress: usize,
+}
+
+/// The pipeline of mutator stages a `Feed` funnels its events through before they reach its ordinary, read-only readers.
+///
+/// As long as no mutator is currently registered — whether none has ever been added, or every one added has since been dropped — this stays out of the way entirely: `Feed::send_with` pushes straight into the broadcast ring, exactly as it did before mutators existed.
+pub(crate) struct Pipeline<Evt> {
+    staging: Mutex<VecDeque<Staged<Evt>>>,
+    /// The total number of stages ever registered. Stage indices are assigned from this and never reused, even once a stage retires, so `Staged::progress` keeps meaning the same thing for the lifetime of the pipeline.
+    stages: AtomicUsize,
+    /// How many registered stages still have a live `Mutator`. Once this reaches zero, `has_stages` reports false again and `Feed::send_with` falls back to pushing straight into the ring, exactly as if no mutator had ever been registered.
+    live_stages: AtomicUsize,
+    /// Whether each registered stage (by index) has had its `Mutator` dropped. A retired stage no longer runs, so events waiting on it are skipped past it instead of blocking there forever.
+    retired: Mutex<Vec<bool>>,
+}
+impl<Evt> Pipeline<Evt> {
+    #[inline]
+    pub(crate) fn new() -> Self {
+        Self {
+            staging: Mutex::new(VecDeque::new()),
+            stages: AtomicUsize::new(0),
+            live_stages: AtomicUsize::new(0),
+            retired: Mutex::new(Vec::new()),
+        }
+    }
+    /// Reserves the next stage index for a newly registered mutator.
+    #[inline]
+    pub(crate) fn register(&self) -> usize {
+        let stage = self.stages.fetch_add(1, Ordering::AcqRel);
+        self.live_stages.fetch_add(1, Ordering::AcqRel);
+        self.retired.lock().push(false);
+        stage
+    }
+    #[inline(always)]
+    pub(crate) fn has_stages(&self) -> bool {
+        self.live_stages.load(Ordering::Acquire) > 0
+    }
+    #[inline]
+    pub(crate) fn push(&self, event: Evt) {
+        self.staging.lock().push_back(Staged {event, progress: 0});
+    }
+    /// Marks a stage as retired because its `Mutator` has been dropped, so events stuck waiting on it can be skipped past it rather than staying at the front of `staging` forever.
+    fn retire(&self, stage: usize) {
+        if let Some(slot) = self.retired.lock().get_mut(stage) {
+            if !*slot {
+                *slot = true;
+                self.live_stages.fetch_sub(1, Ordering::AcqRel);
+            }
+        }
+    }
+    #[inline]
+    fn is_retired(&self, stage: usize) -> bool {
+        self.retired.lock().get(stage).copied().unwrap_or(false)
+    }
+    /// Skips every staged event past any retired stages it's waiting on, then moves every event which has now passed every stage into the feed's ring, tracing and fanning it out to shared reader groups same as `Feed::send_with`. Returns whether anything was delivered.
+    fn deliver_ready(&self, shared: &Shared<Evt>) -> bool
+    where Evt: Send + 'static {
+        let total = self.stages.load(Ordering::Acquire);
+        let mut staging = self.staging.lock();
+        for staged in staging.iter_mut() {
+            while staged.progress < total && self.is_retired(staged.progress) {
+                staged.progress += 1;
+            }
+        }
+        let mut ready = Vec::new();
+        {
+            let mut ring = shared.ring.lock();
+            while let Some(staged) = staging.front() {
+                if staged.progress < total {
+                    break;
+                }
+                let staged = staging.pop_front().expect("front() just returned Some");
+                let id = EventId::new(ring.next_index());
+                let event = Arc::new(staged.event);
+                ring.queue.push_back(Arc::clone(&event));
+                ready.push((id, event));
+            }
+            shared.trim(&mut ring);
+        }
+        let delivered = !ready.is_empty();
+        for (id, event) in &ready {
+            shared.fan_out_to_groups(event);
+            shared.trace(*id);
+        }
+        #[cfg(feature = "futures")]
+        if delivered {
+            shared.wake_all();
+        }
+        delivered
+    }
+}
+
+/// A single stage in a `Feed`'s mutator pipeline, capable of modifying an event in place before later stages and ordinary readers see it.
+///
+/// Mutators are visited in the order they were registered with `Feed::add_mutator`: the first mutator's changes are visible to the second, the second's to the third, and so on, with ordinary readers added via `Feed::add_reader` only ever observing the fully-mutated event once it has passed through every stage.
+pub struct Mutator<Evt>
+where Evt: Send + 'static {
+    pipeline: Arc<Pipeline<Evt>>,
+    shared: Arc<Shared<Evt>>,
+    stage: usize,
+}
+impl<Evt> Mutator<Evt>
+where Evt: Send + 'static {
+    #[inline]
+    pub(crate) fn new(pipeline: Arc<Pipeline<Evt>>, shared: Arc<Shared<Evt>>, stage: usize) -> Self {
+        Self {pipeline, shared, stage}
+    }
+    /// Applies the specified closure to every event which has reached this mutator's stage, in the order they were sent.
+    ///
+    /// Once an event has been visited by every registered mutator, it is moved into the feed's broadcast ring, where ordinary readers can read it like any other event.
+    pub fn mutate_with<F>(&self, mut f: F)
+    where F: FnMut(&mut Evt) {
+        {
+            let mut staging = self.pipeline.staging.lock();
+            for staged in staging.iter_mut() {
+                if staged.progress == self.stage {
+                    f(&mut staged.event);
+                    staged.progress += 1;
+                }
+            }
+        }
+        self.pipeline.deliver_ready(&self.shared);
+    }
+}
+impl<Evt> Drop for Mutator<Evt>
+where Evt: Send + 'static {
+    /// Retires this stage so events which were waiting on it are no longer stuck behind it forever, then delivers anything that can now proceed to the ordinary readers.
+    ///
+    /// Without this, dropping a `Mutator` before every currently-staged event reached it (including never calling `mutate_with` even once) would wedge the whole pipeline: the event at the front of `staging` would sit at this stage's progress count forever, and every event sent afterwards would queue up behind it, never reaching `add_reader`'s readers.
+    fn drop(&mut self) {
+        self.pipeline.retire(self.stage);
+        self.pipeline.deliver_ready(&self.shared);
+    }
+}
+impl<Evt> fmt::Debug for Mutator<Evt>
+where Evt: Send + 'static {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.debug_struct("Mutator")
+            .field("stage", &self.stage)
+        .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Feed;
+
+    #[test]
+    fn dropping_last_mutator_does_not_stall_the_feed() {
+        let mut feed = Feed::<i32>::new();
+        let reader = feed.add_reader();
+        let mutator = feed.add_mutator();
+
+        feed.send(1);
+        mutator.mutate_with(|x| *x += 1);
+        assert_eq!(reader.read().next().as_deref(), Some(&2));
+
+        drop(mutator);
+
+        // Once the only mutator is gone, the feed should behave like a plain broadcast feed again.
+        feed.send(100);
+        assert_eq!(reader.read().next().as_deref(), Some(&100));
+    }
+
+    #[test]
+    fn dropping_a_mutator_before_it_ever_ran_unblocks_staged_events() {
+        let mut feed = Feed::<i32>::new();
+        let reader = feed.add_reader();
+        let mutator = feed.add_mutator();
+        feed.send(1);
+        // Event 1 is now staged, waiting on a mutator which never runs; dropping that mutator
+        // must not leave it stuck in staging forever.
+        drop(mutator);
+        feed.send(2);
+        assert_eq!(reader.read().map(|e| *e).collect::<Vec<_>>(), vec![1, 2]);
+    }
+
+    #[test]
+    fn later_stages_still_gate_delivery_after_an_earlier_stage_retires() {
+        let mut feed = Feed::<i32>::new();
+        let reader = feed.add_reader();
+        let first = feed.add_mutator();
+        let second = feed.add_mutator();
+
+        feed.send(1);
+        drop(first);
+        // The event must still wait on `second`, even though `first` retired without running.
+        assert_eq!(reader.read().next(), None);
+        second.mutate_with(|x| *x *= 10);
+        assert_eq!(reader.read().next().as_deref(), Some(&10));
+    }
+}
@@ -1,80 +1,229 @@
 use std::{
+    sync::{
+        Arc, Weak,
+        atomic::{AtomicUsize, Ordering},
+    },
     collections::VecDeque,
     iter::{
-        Iterator, IntoIterator, DoubleEndedIterator, ExactSizeIterator, FusedIterator,
+        Iterator, IntoIterator, ExactSizeIterator, FusedIterator,
     },
     fmt::{self, Formatter},
 };
-use parking_lot::{
-    Mutex,
-    MutexGuard,
-};
+use parking_lot::Mutex;
+use crate::{EventId, shared_group::GroupQueue};
+
+/// A callback invoked by a `Feed` whenever an event is sent and whenever a reader consumes one, for tracing and metrics.
+pub(crate) type TraceHook<Evt> = Arc<dyn Fn(EventId<Evt>) + Send + Sync>;
+
+/// A reader's read position into the ring, plus (with the `futures` feature enabled) a slot for the waker of whichever task is currently awaiting its next event.
+pub(crate) struct Cursor {
+    position: AtomicUsize,
+    #[cfg(feature = "futures")]
+    pub(crate) waker: Mutex<Option<std::task::Waker>>,
+}
+impl Cursor {
+    #[inline]
+    pub(crate) fn new(position: usize) -> Self {
+        Self {
+            position: AtomicUsize::new(position),
+            #[cfg(feature = "futures")]
+            waker: Mutex::new(None),
+        }
+    }
+    /// Wakes whichever task is parked awaiting this reader's next event, if any.
+    #[cfg(feature = "futures")]
+    #[inline]
+    pub(crate) fn wake(&self) {
+        if let Some(waker) = &*self.waker.lock() {
+            waker.wake_by_ref();
+        }
+    }
+}
+
+/// The ring of events which are still needed by at least one reader, in the order they were sent.
+///
+/// Events are kept behind an `Arc` so that handing one out to several readers never requires `Evt: Clone` nor makes more than the one copy the feed received in the first place.
+pub(crate) struct Ring<Evt> {
+    pub(crate) queue: VecDeque<Arc<Evt>>,
+    /// The absolute index of `queue`'s front element, i.e. how many events have already been trimmed from the front of the ring.
+    pub(crate) base: usize,
+}
+impl<Evt> Ring<Evt> {
+    #[inline]
+    pub(crate) fn with_capacity(capacity: usize) -> Self {
+        Self {
+            queue: VecDeque::with_capacity(capacity),
+            base: 0,
+        }
+    }
+    /// The absolute index which the next sent event will receive.
+    #[inline(always)]
+    pub(crate) fn next_index(&self) -> usize {
+        self.base + self.queue.len()
+    }
+}
+
+/// State shared between a `Feed` and every reader created from it.
+///
+/// Kept alive by an `Arc` for as long as the `Feed` or any of its readers exist, which is what allows a reader to keep reading events which were sent before the `Feed` which created it was dropped.
+pub(crate) struct Shared<Evt>
+where Evt: Send + 'static {
+    pub(crate) ring: Mutex<Ring<Evt>>,
+    /// Cursors of every reader which has not been dropped, used to figure out how far the front of the ring can be trimmed and (with the `futures` feature) who to wake up on send.
+    pub(crate) cursors: Mutex<Vec<Weak<Cursor>>>,
+    /// Queues of every shared reader group which has not been dropped, each of which gets one shared copy of every event sent.
+    pub(crate) groups: Mutex<Vec<Weak<GroupQueue<Evt>>>>,
+    /// Invoked with an event's id whenever it is sent, and again whenever a reader consumes it.
+    pub(crate) trace: Mutex<Option<TraceHook<Evt>>>,
+}
+impl<Evt> Shared<Evt>
+where Evt: Send + 'static {
+    /// Calls the trace hook, if one is set, with the given event id.
+    #[inline]
+    pub(crate) fn trace(&self, id: EventId<Evt>) {
+        if let Some(hook) = &*self.trace.lock() {
+            hook(id);
+        }
+    }
+    /// Drops every event from the front of the ring which has already been seen by every live reader, releasing the slot pressure those readers used to hold.
+    pub(crate) fn trim(&self, ring: &mut Ring<Evt>) {
+        let cursors = self.cursors.lock();
+        let mut min = ring.next_index();
+        for cursor in cursors.iter() {
+            if let Some(cursor) = cursor.upgrade() {
+                min = min.min(cursor.position.load(Ordering::Acquire));
+            }
+        }
+        while ring.base < min {
+            ring.queue.pop_front();
+            ring.base += 1;
+        }
+    }
+    /// Wakes every live reader currently parked awaiting its next event.
+    #[cfg(feature = "futures")]
+    pub(crate) fn wake_all(&self) {
+        for cursor in self.cursors.lock().iter() {
+            if let Some(cursor) = cursor.upgrade() {
+                cursor.wake();
+            }
+        }
+    }
+    /// Gives every live shared reader group one shared copy of the event, for whichever of its subscribers reads it first.
+    pub(crate) fn fan_out_to_groups(&self, event: &Arc<Evt>) {
+        for group in self.groups.lock().iter() {
+            if let Some(group) = group.upgrade() {
+                group.queue.lock().push_back(Arc::clone(event));
+            }
+        }
+    }
+}
 
-/// Recieves events from event feeds and queues them until they are processed.
+/// Recieves events from event feeds by holding a cursor into the feed's shared ring buffer.
+///
+/// Unlike a per-reader queue, a `Reader` does not store events itself — it only remembers how far it has read into the feed it was created from. This means that reading one reader's events does not remove them for any other reader, and no `Clone` bound on the event type is ever needed just to broadcast to several readers.
 pub struct Reader<Evt>
-where Evt: Send {
-    queue: Mutex<VecDeque<Evt>>,
+where Evt: Send + 'static {
+    pub(crate) shared: Arc<Shared<Evt>>,
+    pub(crate) cursor: Arc<Cursor>,
 }
 impl<Evt> Reader<Evt>
-where Evt: Send {
-    /// Creates an iterator which reads and removes the events from the queue.
+where Evt: Send + 'static {
+    /// Creates an iterator which reads every event this reader has not consumed yet, i.e. every event sent since the reader was created which no earlier iterator of this reader has yielded.
     ///
-    /// The queue's mutex remains locked for the entire lifetime of the returned iterator, which means that all calls to the feed's `send_with`, `send` and others will block. If you do not want that behavior, drop the iterator after a number of iterations and create a new one, which should cause a fair mutex unlock if it ran for long enough, allowing the feed to send new events.
-    #[inline(always)]
+    /// This only holds the ring's mutex long enough to snapshot the pending events into the iterator's own buffer; the feed's `send_with` and every other reader's `read` are free to run concurrently with it rather than blocking for as long as the returned iterator lives.
+    #[inline]
     pub fn read(&self) -> ReaderIter<'_, Evt> {
+        let ring = self.shared.ring.lock();
+        let position = self.cursor.position.load(Ordering::Acquire).max(ring.base);
+        let events = ring.queue.iter().skip(position - ring.base).cloned().collect::<Vec<_>>();
+        drop(ring);
         ReaderIter {
-            queue: self.queue.lock()
+            shared: &self.shared,
+            cursor: &self.cursor,
+            #[cfg(feature = "futures")]
+            start: position,
+            position,
+            events: events.into_iter(),
         }
     }
-    /// Reads the entire queue by using the specified closure to process the events. Useful for simple event handling, i.e. if the closure doesn't return anything depending on how it processes the events. If it does, using `read` directly is necesarry.
+    /// Reads every pending event by using the specified closure to process them. Useful for simple event handling, i.e. if the closure doesn't return anything depending on how it processes the events. If it does, using `read` directly is necesarry.
     ///
     /// See `read` for the mutex-related implications of using this.
     #[inline(always)]
     pub fn read_with<F>(&self, f: F)
-    where F: FnMut(Evt) {
+    where F: FnMut(Arc<Evt>) {
         self.read().for_each(f);
     }
-    /// Creates a reader.
+    /// Like `read`, but the iterator also yields the `EventId` of each event, letting you follow it from `Feed::send` through every reader which consumes it.
     ///
-    /// This method is not meant to be exposed to library users. The correct method which you should use for creating readers is `Feed`'s `add_reader`.
-    #[inline(always)]
-    pub(crate) fn new() -> Self {
-        Self {
-            queue: Mutex::new(VecDeque::new()),
+    /// See `read` for the mutex-related implications of using this.
+    #[inline]
+    pub fn read_with_ids(&self) -> ReaderIdsIter<'_, Evt> {
+        let ring = self.shared.ring.lock();
+        let position = self.cursor.position.load(Ordering::Acquire).max(ring.base);
+        let events = ring.queue.iter().skip(position - ring.base).cloned().collect::<Vec<_>>();
+        drop(ring);
+        ReaderIdsIter {
+            inner: ReaderIter {
+                shared: &self.shared,
+                cursor: &self.cursor,
+                #[cfg(feature = "futures")]
+                start: position,
+                position,
+                events: events.into_iter(),
+            },
         }
     }
-    /// Recieves the specified event by putting it into the queue.
+    /// Creates a reader which reads from the feed's ring through the given cursor, starting at whatever position the cursor holds.
     ///
-    /// This method is not meant to be exposed to library users. The only place where it should be called is `Feed`'s `send_with` and its variations.
+    /// This method is not meant to be exposed to library users. The correct method which you should use for creating readers is `Feed`'s `add_reader`.
     #[inline]
-    pub(crate) fn recieve(&self, event: Evt) {
-        let mut queue = self.queue.lock();
-        queue.push_back(event);
+    pub(crate) fn new(shared: Arc<Shared<Evt>>, cursor: Arc<Cursor>) -> Self {
+        Self {shared, cursor}
     }
 }
 impl<Evt> fmt::Debug for Reader<Evt>
-where Evt: fmt::Debug + Send {
+where Evt: Send + 'static {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
         f.debug_struct("Reader")
-            .field("queue", &*self.queue.lock())
+            .field("cursor", &self.cursor.position.load(Ordering::Relaxed))
         .finish()
     }
 }
 
-/// An iterator used for processing events in an event reader's queue.
+/// An iterator used for processing the events pending for an event reader.
 ///
-/// Use the `read` method to acquire this.
+/// Use the `read` method to acquire this. The events it yields were snapshotted out of the ring when it was created, so the ring's mutex is not held while the iterator is processed.
 pub struct ReaderIter<'r, Evt>
-where Evt: Send {
-    queue: MutexGuard<'r, VecDeque<Evt>>,
+where Evt: Send + 'static {
+    shared: &'r Shared<Evt>,
+    cursor: &'r Cursor,
+    /// The reader's position when this iterator was created, used by `Drop` to tell whether anything was actually consumed.
+    #[cfg(feature = "futures")]
+    start: usize,
+    /// The absolute index of the next event `events` will yield.
+    position: usize,
+    events: std::vec::IntoIter<Arc<Evt>>,
+}
+impl<'r, Evt> ReaderIter<'r, Evt>
+where Evt: Send + 'static {
+    /// Pops the next event along with the id it was sent with, tracing its consumption if a hook is set.
+    #[inline]
+    fn next_with_id(&mut self) -> Option<(EventId<Evt>, Arc<Evt>)> {
+        let event = self.events.next()?;
+        let id = EventId::new(self.position);
+        self.position += 1;
+        self.shared.trace(id);
+        Some((id, event))
+    }
 }
 impl<'r, Evt> Iterator for ReaderIter<'r, Evt>
-where Evt: Send {
-    type Item = Evt;
-    
-    #[inline(always)]
-    fn next(&mut self) -> Option<Evt> {
-        self.queue.pop_front()
+where Evt: Send + 'static {
+    type Item = Arc<Evt>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Arc<Evt>> {
+        self.next_with_id().map(|(_, event)| event)
     }
     #[inline(always)]
     fn size_hint(&self) -> (usize, Option<usize>) {
@@ -88,19 +237,58 @@ where Evt: Send {
         self.len()
     }
 }
-impl<'r, Evt> DoubleEndedIterator for ReaderIter<'r, Evt>
-where Evt: Send {
+impl<'r, Evt> ExactSizeIterator for ReaderIter<'r, Evt>
+where Evt: Send + 'static {
     #[inline(always)]
-    fn next_back(&mut self) -> Option<Evt> {
-        self.queue.pop_back()
+    fn len(&self) -> usize {
+        self.events.len()
     }
 }
-impl<'r, Evt> ExactSizeIterator for ReaderIter<'r, Evt>
-where Evt: Send {
+impl<'r, Evt> FusedIterator for ReaderIter<'r, Evt>
+where Evt: Send + 'static {}
+impl<'r, Evt> Drop for ReaderIter<'r, Evt>
+where Evt: Send + 'static {
+    /// Advances the reader's cursor past every event this iterator actually yielded (events it did not yield stay pending for the next `read`), then releases every event no live reader needs any more.
+    fn drop(&mut self) {
+        self.cursor.position.store(self.position, Ordering::Release);
+        #[cfg(feature = "futures")]
+        if self.position > self.start {
+            // Only wake parked readers if an event actually advanced past this one's cursor;
+            // otherwise every no-op `read()` (as `Stream::poll_next` performs on every poll)
+            // would rewake every waker it just registered, spinning the executor.
+            self.shared.wake_all();
+        }
+        let mut ring = self.shared.ring.lock();
+        self.shared.trim(&mut ring);
+    }
+}
+
+/// An iterator used for processing the events pending for an event reader along with the `EventId` of each one.
+///
+/// Use the `read_with_ids` method to acquire this.
+pub struct ReaderIdsIter<'r, Evt>
+where Evt: Send + 'static {
+    inner: ReaderIter<'r, Evt>,
+}
+impl<'r, Evt> Iterator for ReaderIdsIter<'r, Evt>
+where Evt: Send + 'static {
+    type Item = (EventId<Evt>, Arc<Evt>);
+
+    #[inline(always)]
+    fn next(&mut self) -> Option<(EventId<Evt>, Arc<Evt>)> {
+        self.inner.next_with_id()
+    }
+    #[inline(always)]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+impl<'r, Evt> ExactSizeIterator for ReaderIdsIter<'r, Evt>
+where Evt: Send + 'static {
     #[inline(always)]
     fn len(&self) -> usize {
-        self.queue.len()
+        self.inner.len()
     }
 }
-impl<Evt> FusedIterator for ReaderIter<'_, Evt>
-where Evt: Send {}
\ No newline at end of file
+impl<'r, Evt> FusedIterator for ReaderIdsIter<'r, Evt>
+where Evt: Send + 'static {}
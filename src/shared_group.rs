@@ -0,0 +1,108 @@
+use std::{
+    collections::VecDeque,
+    sync::Arc,
+    fmt::{self, Formatter},
+};
+use parking_lot::Mutex;
+
+/// The queue backing a `SharedReaderGroup`, kept alive by an `Arc` for as long as the group or any of its subscribers exist.
+pub(crate) struct GroupQueue<Evt> {
+    pub(crate) queue: Mutex<VecDeque<Arc<Evt>>>,
+}
+impl<Evt> GroupQueue<Evt> {
+    #[inline]
+    pub(crate) fn new() -> Self {
+        Self {
+            queue: Mutex::new(VecDeque::new()),
+        }
+    }
+}
+
+/// A work-sharing subscription on a `Feed`, created with `Feed::add_shared_reader_group`.
+///
+/// Unlike an ordinary `Reader`, which broadcasts every event to every reader, a shared reader group hands each sent event to exactly one of its subscribers — whichever one calls `try_read` first — making it suitable for spreading work across a pool of worker threads rather than notifying every one of them. A feed can have any number of broadcast readers and shared reader groups at once: each send fans out one event to every broadcast reader and one shared copy to each group.
+pub struct SharedReaderGroup<Evt>
+where Evt: Send + 'static {
+    pub(crate) queue: Arc<GroupQueue<Evt>>,
+}
+impl<Evt> SharedReaderGroup<Evt>
+where Evt: Send + 'static {
+    /// Creates a new subscription to this group, able to claim events from the group's shared queue independently of every other subscriber.
+    #[inline]
+    pub fn subscribe(&self) -> Arc<SharedReader<Evt>> {
+        Arc::new(SharedReader {
+            queue: Arc::clone(&self.queue),
+        })
+    }
+}
+impl<Evt> fmt::Debug for SharedReaderGroup<Evt>
+where Evt: Send + 'static {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.debug_struct("SharedReaderGroup")
+            .field("pending", &self.queue.queue.lock().len())
+        .finish()
+    }
+}
+
+/// One worker's subscription to a `SharedReaderGroup`, created with `SharedReaderGroup::subscribe`.
+///
+/// Every subscriber of the same group pops from the same underlying queue, so each event sent to the group is delivered to exactly one subscriber, whichever claims it first — there is no notion of "catching up" the way there is for a broadcast `Reader`.
+pub struct SharedReader<Evt>
+where Evt: Send + 'static {
+    queue: Arc<GroupQueue<Evt>>,
+}
+impl<Evt> SharedReader<Evt>
+where Evt: Send + 'static {
+    /// Claims the next event in the group's queue, if one is waiting, removing it so that no other subscriber of the group will see it.
+    #[inline]
+    pub fn try_read(&self) -> Option<Arc<Evt>> {
+        self.queue.queue.lock().pop_front()
+    }
+}
+impl<Evt> fmt::Debug for SharedReader<Evt>
+where Evt: Send + 'static {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.debug_struct("SharedReader")
+            .field("pending", &self.queue.queue.lock().len())
+        .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Feed;
+
+    #[test]
+    fn each_event_goes_to_exactly_one_subscriber() {
+        let mut feed = Feed::<i32>::new();
+        let group = feed.add_shared_reader_group();
+        let worker_a = group.subscribe();
+        let worker_b = group.subscribe();
+
+        feed.send(1);
+        feed.send(2);
+
+        let from_a = worker_a.try_read();
+        let from_b = worker_b.try_read();
+        assert_eq!(
+            [from_a.as_deref(), from_b.as_deref()].into_iter().flatten().collect::<Vec<_>>(),
+            vec![&1, &2],
+            "the two events must be split exactly one-to-one across the group's subscribers",
+        );
+        assert_eq!(worker_a.try_read(), None);
+        assert_eq!(worker_b.try_read(), None);
+    }
+
+    #[test]
+    fn broadcast_readers_and_shared_groups_each_get_their_own_copy() {
+        let mut feed = Feed::<i32>::new();
+        let broadcast = feed.add_reader();
+        let group = feed.add_shared_reader_group();
+        let worker = group.subscribe();
+
+        feed.send(1);
+
+        assert_eq!(broadcast.read().next().as_deref(), Some(&1));
+        assert_eq!(worker.try_read().as_deref(), Some(&1));
+    }
+}
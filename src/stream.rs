@@ -0,0 +1,48 @@
+//! `futures::Stream` integration, enabled by the `futures` Cargo feature.
+//!
+//! With this feature enabled, a reader can be `.await`ed for its next event instead of polling `read` in a loop, which lets readers plug directly into `tokio`/`async-std`/`smol` executors and compose with `select!`, timeouts, and the rest of the `futures` combinators.
+
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+use futures::Stream;
+use crate::Reader;
+
+/// Reads a `Reader`'s events as a `futures::Stream` rather than by polling `read` yourself.
+///
+/// The stream never ends: it stays `Pending` until a new event is sent, registering the polling task's waker so the feed can wake it up again. Since `Reader` is meant to be shared behind an `Arc`, the stream is implemented for `&Reader<Evt>` rather than `Reader<Evt>` itself — `&Reader<Evt>` is always `Unpin`, so no pinning gymnastics are required on the caller's end.
+impl<Evt> Stream for &Reader<Evt>
+where Evt: Send + 'static {
+    type Item = std::sync::Arc<Evt>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let reader = *self.get_mut();
+        // Register interest before checking for an event, so that an event sent between the
+        // check below and the caller parking the task is not missed.
+        *reader.cursor.waker.lock() = Some(cx.waker().clone());
+        match reader.read().next() {
+            Some(event) => Poll::Ready(Some(event)),
+            None => Poll::Pending,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Feed;
+    use futures::StreamExt;
+
+    #[test]
+    fn stream_yields_already_pending_events_in_order() {
+        futures::executor::block_on(async {
+            let mut feed = Feed::<i32>::new();
+            let reader = feed.add_reader();
+            feed.send(1);
+            feed.send(2);
+            let mut stream = &*reader;
+            assert_eq!(stream.next().await, Some(std::sync::Arc::new(1)));
+            assert_eq!(stream.next().await, Some(std::sync::Arc::new(2)));
+        });
+    }
+}